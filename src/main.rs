@@ -20,14 +20,30 @@
 // rayon = "1.7"
 // tempfile = "3"
 // dirs = "4"
+// sha2 = "0.10"
+// notify = "6"
+// quick-xml = "0.31"
+// ignore = "0.4"
 //
 // Comportamento:
 // - Percorre recursivamente --root (padrão: $HOME)
 // - Se encontrar .zip, extrai em um tempdir com proteção contra path traversal
-// - Procura em nomes de arquivos um bloco de 44 dígitos (primeiro encontrado)
+// - Procura em nomes de arquivos um bloco de 44 dígitos (primeiro encontrado);
+//   se o nome não tiver, faz fallback lendo o conteúdo do XML (Id de infNFe/
+//   infCte/infMDFe ou elementos chNFe/chCTe)
 // - model = chave[20..22], mapeado para 55->NFe, 57->CTe, 58->MDFe, 65->NFCe
 // - Para 55 e 57, interpreta ano (chave[2..4]) e mês (chave[4..6]) e decide "Mais de 6 meses" vs "Menos de 6 meses"
 // - Copia arquivos (não move), criando diretórios e evitando sobrescrita adicionando sufixos _1, _2...
+// - Mantém um cache de varredura (.organizar_state dentro de --dest) para pular,
+//   em execuções futuras, arquivos cujo tamanho e mtime não mudaram
+// - Deduplica por conteúdo (SHA-256, índice em .organizar_digest_index): um XML
+//   byte-idêntico já armazenado não é copiado de novo, mesmo com outro nome
+// - Com --watch, fica em execução monitorando --root e organiza novos arquivos
+//   assim que param de ser escritos (debounce), em vez de varrer uma única vez
+// - Com --validate, confere o DV mod-11 da chave e desvia chaves inválidas
+//   para Invalidas/ em vez das pastas NFe/CTe/MDFe/NFCe normais
+// - Honra arquivos .fiscalignore (sintaxe .gitignore) descobertos em cada
+//   diretório e globs --exclude repetíveis, podando subárvores na varredura
 
 use clap::Parser;
 use chrono::{Datelike, Local};
@@ -37,9 +53,13 @@ use std::fs;
 use walkdir::WalkDir;
 use zip::read::ZipArchive;
 use std::fs::File;
-use tempfile::tempdir;
+use tempfile::{tempdir, TempDir};
 use rayon::prelude::*;
-use std::io::{self};
+use std::io::{self, BufRead, Write};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Organiza XML (NFe/CTe/MDFe/NFCe) por chave de acesso", long_about = None)]
@@ -63,10 +83,96 @@ struct Args {
     /// Verbose
     #[arg(short, long)]
     verbose: bool,
+
+    /// Modo contínuo: monitora --root e organiza novos arquivos .xml/.zip assim que chegam
+    #[arg(long)]
+    watch: bool,
+
+    /// Valida o dígito verificador (mod-11) da chave antes de arquivar; chaves
+    /// inválidas vão para Invalidas/ dentro de --dest
+    #[arg(long)]
+    validate: bool,
+
+    /// Padrão (estilo .gitignore) a excluir da varredura; pode repetir a flag
+    #[arg(long = "exclude")]
+    excludes: Vec<String>,
 }
 
 const CHAVE_RE_STR: &str = r"(\d{44})";
 
+// Nome do arquivo de estado persistido dentro de --dest, usado para pular
+// arquivos já processados em execuções anteriores (estilo "dirstate").
+const STATE_FILENAME: &str = ".organizar_state";
+
+#[derive(Clone, Debug)]
+struct ScanEntry {
+    size: u64,
+    mtime: i64,
+    dest: String,
+}
+
+type ScanState = HashMap<PathBuf, ScanEntry>;
+
+fn load_scan_state(dest_base: &Path, verbose: bool) -> ScanState {
+    let mut state = ScanState::new();
+    let path = dest_base.join(STATE_FILENAME);
+    let file = match File::open(&path) {
+        Ok(f) => f,
+        Err(_) => return state,
+    };
+    for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+        let mut parts = line.splitn(4, '\t');
+        if let (Some(p), Some(size), Some(mtime), Some(dest)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        {
+            if let (Ok(size), Ok(mtime)) = (size.parse::<u64>(), mtime.parse::<i64>()) {
+                state.insert(PathBuf::from(p), ScanEntry { size, mtime, dest: dest.to_string() });
+            }
+        }
+    }
+    if verbose {
+        println!("[DEBUG] Estado carregado de {} ({} entradas)", path.display(), state.len());
+    }
+    state
+}
+
+fn save_scan_state(dest_base: &Path, state: &ScanState, verbose: bool) -> io::Result<()> {
+    let path = dest_base.join(STATE_FILENAME);
+    let mut file = File::create(&path)?;
+    for (src, entry) in state {
+        writeln!(file, "{}\t{}\t{}\t{}", src.display(), entry.size, entry.mtime, entry.dest)?;
+    }
+    if verbose {
+        println!("[DEBUG] Estado salvo em {} ({} entradas)", path.display(), state.len());
+    }
+    Ok(())
+}
+
+fn file_size_mtime(path: &Path) -> io::Result<(u64, i64)> {
+    let meta = fs::metadata(path)?;
+    let mtime = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    Ok((meta.len(), mtime))
+}
+
+/// Decide se `path` pode ser reaproveitado do estado salvo. Um arquivo cujo
+/// mtime seja igual ou posterior ao instante em que a varredura começou é
+/// tratado como "ambíguo/sujo" e sempre reprocessado, pois não há garantia
+/// de que sua escrita tenha terminado antes do momento de comparação.
+fn is_unchanged(path: &Path, state: &ScanState, scan_start: i64) -> Option<(u64, i64)> {
+    let (size, mtime) = file_size_mtime(path).ok()?;
+    if mtime >= scan_start {
+        return None;
+    }
+    match state.get(path) {
+        Some(entry) if entry.size == size && entry.mtime == mtime => Some((size, mtime)),
+        _ => None,
+    }
+}
+
 fn ensure_directories(base: &Path, verbose: bool) -> io::Result<()> {
     let structure = vec![
         "CTe",
@@ -90,10 +196,145 @@ fn ensure_directories(base: &Path, verbose: bool) -> io::Result<()> {
     Ok(())
 }
 
+// Nome do arquivo de exclusão, estilo .gitignore, procurado em cada diretório
+// percorrido (além dos globs repetíveis passados via --exclude).
+const IGNORE_FILENAME: &str = ".fiscalignore";
+
+/// Monta o `Gitignore` global a partir dos globs `--exclude`, sem depender de
+/// nenhum diretório específico do disco (usa `root` apenas como base de paths
+/// relativos).
+fn build_global_ignore(root: &Path, excludes: &[String]) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in excludes {
+        let _ = builder.add_line(None, pattern);
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// Compõe, para `dir`, a cadeia de `Gitignore` herdada de cada ancestral até
+/// `root` (cada um construído a partir do `.fiscalignore` daquele diretório,
+/// se existir), com memoização em `cache` para não reler o mesmo arquivo a
+/// cada entrada da varredura.
+fn ignore_chain_for_dir(dir: &Path, root: &Path, cache: &mut HashMap<PathBuf, Vec<Gitignore>>) -> Vec<Gitignore> {
+    if let Some(chain) = cache.get(dir) {
+        return chain.clone();
+    }
+    let mut chain = if dir == root {
+        Vec::new()
+    } else {
+        match dir.parent() {
+            Some(parent) => ignore_chain_for_dir(parent, root, cache),
+            None => Vec::new(),
+        }
+    };
+    let ignore_file = dir.join(IGNORE_FILENAME);
+    if ignore_file.is_file() {
+        let mut builder = GitignoreBuilder::new(dir);
+        if builder.add(&ignore_file).is_none() {
+            if let Ok(gitignore) = builder.build() {
+                chain.push(gitignore);
+            }
+        }
+    }
+    cache.insert(dir.to_path_buf(), chain.clone());
+    chain
+}
+
+/// Decide se `path` deve ser ignorado, aplicando primeiro os `--exclude`
+/// globais e depois a cadeia de `.fiscalignore` da raiz até o diretório pai de
+/// `path`, na ordem (regras mais específicas, mais próximas de `path`, podem
+/// reverter as de ancestrais, como no git).
+fn is_path_ignored(path: &Path, is_dir: bool, global: &Gitignore, chain: &[Gitignore]) -> bool {
+    let mut ignored = matches!(global.matched(path, is_dir), ignore::Match::Ignore(_));
+    for gitignore in chain {
+        match gitignore.matched(path, is_dir) {
+            ignore::Match::Ignore(_) => ignored = true,
+            ignore::Match::Whitelist(_) => ignored = false,
+            ignore::Match::None => {}
+        }
+    }
+    ignored
+}
+
 fn find_chave_in_name(name: &str, re: &Regex) -> Option<String> {
     re.captures(name).and_then(|cap| cap.get(1).map(|m| m.as_str().to_string()))
 }
 
+/// Extrai o prefixo de modelo ("NFe", "CTe", "MDFe") de um atributo `Id` como
+/// `NFe35200714200166000187550010000000046550000046` e valida os 44 dígitos.
+fn chave_from_id_attr(id: &str, re: &Regex) -> Option<String> {
+    let stripped = id
+        .strip_prefix("NFe")
+        .or_else(|| id.strip_prefix("CTe"))
+        .or_else(|| id.strip_prefix("MDFe"))
+        .unwrap_or(id);
+    find_chave_in_name(stripped, re)
+}
+
+/// Fallback para quando o nome do arquivo não contém a chave: faz um parse em
+/// streaming do XML (sem carregar a árvore inteira) procurando o atributo
+/// `Id` de `infNFe`/`infCte`/`infMDFe` ou os elementos `chNFe`/`chCTe`.
+fn find_chave_in_xml_content(path: &Path, re: &Regex) -> Option<String> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_file(path).ok()?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut inside_chave_elem = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let local_name = local_element_name(e.name().as_ref());
+                if matches!(local_name.as_str(), "infNFe" | "infCte" | "infMDFe") {
+                    for attr in e.attributes().filter_map(|a| a.ok()) {
+                        if attr.key.as_ref() == b"Id" {
+                            if let Ok(value) = attr.unescape_value() {
+                                if let Some(chave) = chave_from_id_attr(&value, re) {
+                                    return Some(chave);
+                                }
+                            }
+                        }
+                    }
+                }
+                inside_chave_elem = matches!(local_name.as_str(), "chNFe" | "chCTe");
+            }
+            Ok(Event::Text(e)) => {
+                if inside_chave_elem
+                    && let Ok(text) = e.unescape()
+                    && let Some(chave) = find_chave_in_name(&text, re)
+                {
+                    return Some(chave);
+                }
+            }
+            Ok(Event::End(_)) => inside_chave_elem = false,
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    None
+}
+
+fn local_element_name(qualified: &[u8]) -> String {
+    let name = String::from_utf8_lossy(qualified);
+    name.rsplit(':').next().unwrap_or(&name).to_string()
+}
+
+/// Tenta achar a chave primeiro pelo nome do arquivo e, se falhar, faz o
+/// fallback de ler o conteúdo do XML (ver `find_chave_in_xml_content`).
+fn find_chave_for_xml(path: &Path, name: &str, re: &Regex, verbose: bool) -> Option<String> {
+    if let Some(chave) = find_chave_in_name(name, re) {
+        return Some(chave);
+    }
+    if verbose {
+        println!("[DEBUG] Nenhuma chave no nome, lendo conteúdo XML: {}", path.display());
+    }
+    find_chave_in_xml_content(path, re)
+}
+
 fn six_months_ago_reference(today: chrono::NaiveDate) -> chrono::NaiveDate {
     // Retorna o primeiro dia do mês que representa 6 meses atrás
     let mut year = today.year();
@@ -181,12 +422,157 @@ fn determine_destination_for_xml(base: &Path, chave: &str, today: chrono::NaiveD
     }
 }
 
-fn unique_dest(dest_dir: &Path, file_name: &str) -> Option<PathBuf> {
-    let candidate = dest_dir.join(file_name);
-    if !candidate.exists() {
-        return Some(candidate);
-    } 
-    return None;
+// Nome do arquivo que guarda, dentro de --dest, o índice de digests SHA-256
+// já armazenados (content-addressed store), permitindo detectar duplicatas
+// de conteúdo mesmo quando o nome do arquivo de origem muda.
+const DIGEST_INDEX_FILENAME: &str = ".organizar_digest_index";
+
+type DigestIndex = HashMap<String, PathBuf>;
+
+fn load_digest_index(dest_base: &Path, verbose: bool) -> DigestIndex {
+    let mut index = DigestIndex::new();
+    let path = dest_base.join(DIGEST_INDEX_FILENAME);
+    let file = match File::open(&path) {
+        Ok(f) => f,
+        Err(_) => return index,
+    };
+    for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+        if let Some((digest, dest)) = line.split_once('\t') {
+            index.insert(digest.to_string(), PathBuf::from(dest));
+        }
+    }
+    if verbose {
+        println!("[DEBUG] Índice de digests carregado de {} ({} entradas)", path.display(), index.len());
+    }
+    index
+}
+
+fn save_digest_index(dest_base: &Path, index: &DigestIndex, verbose: bool) -> io::Result<()> {
+    let path = dest_base.join(DIGEST_INDEX_FILENAME);
+    let mut file = File::create(&path)?;
+    for (digest, dest) in index {
+        writeln!(file, "{}\t{}", digest, dest.display())?;
+    }
+    if verbose {
+        println!("[DEBUG] Índice de digests salvo em {} ({} entradas)", path.display(), index.len());
+    }
+    Ok(())
+}
+
+fn sha256_hex(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    let digest = hasher.finalize();
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Calcula o dígito verificador mod-11 de uma chave de acesso a partir dos
+/// seus 43 primeiros dígitos: soma, da direita para a esquerda, cada dígito
+/// multiplicado pelo peso cíclico 2,3,4,5,6,7,8,9 (reiniciando em 2 após o 9);
+/// `11 - (soma % 11)` é o DV esperado, exceto que 10 e 11 viram 0.
+fn compute_mod11_dv(digits43: &str) -> Option<u32> {
+    if digits43.len() != 43 {
+        return None;
+    }
+    let mut weight = 2u32;
+    let mut sum = 0u32;
+    for c in digits43.chars().rev() {
+        let d = c.to_digit(10)?;
+        sum += d * weight;
+        weight = if weight == 9 { 2 } else { weight + 1 };
+    }
+    let remainder = sum % 11;
+    Some(if remainder == 0 || remainder == 1 { 0 } else { 11 - remainder })
+}
+
+/// Confere se o 44º dígito de `chave` bate com o DV mod-11 dos 43 primeiros.
+fn validate_chave_dv(chave: &str, verbose: bool) -> bool {
+    if chave.len() != 44 {
+        return false;
+    }
+    let (head, tail) = chave.split_at(43);
+    let expected = match compute_mod11_dv(head) {
+        Some(dv) => dv,
+        None => return false,
+    };
+    let found = match tail.chars().next().and_then(|c| c.to_digit(10)) {
+        Some(dv) => dv,
+        None => return false,
+    };
+    if verbose {
+        println!("[DEBUG] DV mod-11 da chave {}: esperado={} encontrado={}", chave, expected, found);
+    }
+    expected == found
+}
+
+/// Decide o diretório de destino para uma chave já extraída: se `--validate`
+/// estiver ligado e o DV mod-11 não bater, arquiva em Invalidas/ dentro de
+/// `base` em vez do fluxo normal de `determine_destination_for_xml`.
+fn destination_for_chave(base: &Path, chave: &str, today: chrono::NaiveDate, validate: bool, verbose: bool) -> Option<PathBuf> {
+    if validate && !validate_chave_dv(chave, verbose) {
+        if verbose {
+            eprintln!("[WARN] chave com DV mod-11 inválido, movendo para Invalidas/: {}", chave);
+        }
+        let dest = base.join("Invalidas");
+        if !dest.exists() {
+            if let Err(e) = fs::create_dir_all(&dest) {
+                eprintln!("[ERROR] falha criando diretório {}: {}", dest.display(), e);
+                return None;
+            }
+        }
+        return Some(dest);
+    }
+    determine_destination_for_xml(base, chave, today, verbose)
+}
+
+/// Gera o candidato de destino número `i` para `file_name` dentro de
+/// `dest_dir`: `i == 0` é o próprio nome, `i >= 1` acrescenta o sufixo
+/// `_1`, `_2`, ... antes da extensão.
+fn suffixed_candidate(dest_dir: &Path, file_name: &str, i: u32) -> PathBuf {
+    if i == 0 {
+        return dest_dir.join(file_name);
+    }
+    let name_path = Path::new(file_name);
+    let stem = name_path.file_stem().and_then(|s| s.to_str()).unwrap_or(file_name);
+    let ext = name_path.extension().and_then(|s| s.to_str());
+    let name = match ext {
+        Some(ext) => format!("{}_{}.{}", stem, i, ext),
+        None => format!("{}_{}", stem, i),
+    };
+    dest_dir.join(name)
+}
+
+/// Escolhe o caminho de destino para `file_name` dentro de `dest_dir` sem
+/// reservá-lo (usado apenas para a prévia em `--dry-run`, onde nada é
+/// realmente escrito). Quando já existe um arquivo com o mesmo nome, gera
+/// sufixos `_1`, `_2`, ... até achar um nome livre, em vez de descartar o
+/// arquivo em silêncio.
+fn unique_dest(dest_dir: &Path, file_name: &str) -> PathBuf {
+    let mut i = 0u32;
+    loop {
+        let candidate = suffixed_candidate(dest_dir, file_name, i);
+        if !candidate.exists() {
+            return candidate;
+        }
+        i += 1;
+    }
+}
+
+/// Igual a `unique_dest`, mas reserva o nome atomicamente criando o arquivo
+/// com `O_EXCL` (via `create_new`). Evita que dois workers do rayon, operando
+/// sobre arquivos de conteúdo diferente que colidem no nome, observem ambos
+/// "nome livre" para o mesmo candidato e um sobrescreva o outro.
+fn reserve_dest_file(dest_dir: &Path, file_name: &str) -> io::Result<(PathBuf, File)> {
+    let mut i = 0u32;
+    loop {
+        let candidate = suffixed_candidate(dest_dir, file_name, i);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(file) => return Ok((candidate, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => i += 1,
+            Err(e) => return Err(e),
+        }
+    }
 }
 
 fn safe_extract_zip(zip_path: &Path, extract_to: &Path, verbose: bool) -> io::Result<Vec<PathBuf>> {
@@ -217,90 +603,429 @@ fn safe_extract_zip(zip_path: &Path, extract_to: &Path, verbose: bool) -> io::Re
     Ok(extracted_paths)
 }
 
-fn copy_file_to_dest(src: &Path, dest_dir: &Path, dry_run: bool, verbose: bool) -> Option<io::Result<PathBuf>> {
+fn copy_file_to_dest(
+    src: &Path,
+    dest_dir: &Path,
+    digest_index: &std::sync::Mutex<DigestIndex>,
+    dry_run: bool,
+    verbose: bool,
+) -> Option<io::Result<PathBuf>> {
     let file_name = src.file_name().and_then(|s| s.to_str()).unwrap_or("file.xml");
-    let dest = unique_dest(dest_dir, file_name);
-    
-    match dest {
-        None => None,
-        Some(dest) => {
-            if verbose {
-                println!("[INFO] Copiando '{}' -> '{}'", src.display(), dest.display());
+
+    let digest = match sha256_hex(src) {
+        Ok(d) => d,
+        Err(e) => return Some(Err(e)),
+    };
+
+    // Checa e reserva o digest sob o mesmo lock: se só o "get" estivesse
+    // protegido, duas threads com conteúdo idêntico poderiam passar ambas
+    // pelo "ainda não visto" antes de qualquer uma registrar o digest,
+    // copiando o mesmo conteúdo duas vezes. O marcador (PathBuf vazio) é
+    // substituído pelo destino real (ou removido, se a cópia falhar) logo
+    // abaixo, sob o mesmo mutex.
+    let already_claimed = {
+        let mut index = digest_index.lock().unwrap();
+        match index.get(&digest).cloned() {
+            Some(existing) => Some(existing),
+            None => {
+                index.insert(digest.clone(), PathBuf::new());
+                None
+            }
+        }
+    };
+    if let Some(existing) = already_claimed {
+        if verbose {
+            if existing.as_os_str().is_empty() {
+                println!("[INFO] Conteúdo idêntico já sendo copiado por outra tarefa, pulando duplicata: {}", src.display());
+            } else {
+                println!(
+                    "[INFO] Conteúdo idêntico já armazenado em '{}', pulando duplicata: {}",
+                    existing.display(),
+                    src.display()
+                );
+            }
+        }
+        return None;
+    }
+
+    if dry_run {
+        // Em --dry-run nada é escrito, então só prevemos o primeiro nome livre;
+        // a reserva atômica de verdade só acontece na cópia real abaixo.
+        let dest = unique_dest(dest_dir, file_name);
+        if verbose {
+            println!("[INFO] Copiando '{}' -> '{}'", src.display(), dest.display());
+        }
+        // Substitui o marcador pela pré-visualização, para que duplicatas
+        // encontradas depois no mesmo dry-run mostrem o destino previsto.
+        digest_index.lock().unwrap().insert(digest, dest.clone());
+        return Some(Ok(dest));
+    }
+
+    let (dest, mut dest_file) = match reserve_dest_file(dest_dir, file_name) {
+        Ok(reserved) => reserved,
+        Err(e) => {
+            digest_index.lock().unwrap().remove(&digest);
+            return Some(Err(e));
+        }
+    };
+
+    if verbose {
+        println!("[INFO] Copiando '{}' -> '{}'", src.display(), dest.display());
+    }
+
+    let copy_result = File::open(src).and_then(|mut src_file| io::copy(&mut src_file, &mut dest_file));
+
+    match copy_result {
+        Ok(_) => {
+            // Atualiza a reserva para o destino real, agora que a cópia
+            // realmente terminou.
+            digest_index.lock().unwrap().insert(digest, dest.clone());
+            Some(Ok(dest))
+        }
+        Err(e) => {
+            // Libera a reserva: a cópia falhou, então esse conteúdo não está
+            // armazenado em lugar nenhum e deve poder ser tentado de novo,
+            // em vez de ficar permanentemente marcado como "já visto".
+            digest_index.lock().unwrap().remove(&digest);
+            let _ = fs::remove_file(&dest);
+            Some(Err(e))
+        }
+    }
+}
+
+/// Para um único arquivo `.xml` ou `.zip`, descobre para onde cada XML
+/// encontrado deve ir e devolve as tarefas de cópia resultantes. Usado tanto
+/// pela varredura em lote (`process_root`) quanto pelo modo `--watch`.
+///
+/// Para `.zip`, as tarefas apontam para arquivos dentro de um `tempdir()`; a
+/// cópia de verdade só acontece depois, em `run_copy_tasks`. Por isso o
+/// `TempDir` é devolvido junto (em vez de ser destruído ao sair do escopo):
+/// o chamador precisa mantê-lo vivo até `run_copy_tasks` terminar, senão os
+/// arquivos extraídos somem antes de serem copiados.
+fn collect_tasks_for_path(
+    path: &Path,
+    base: &Path,
+    re: &Regex,
+    today: chrono::NaiveDate,
+    validate: bool,
+    verbose: bool,
+) -> (Vec<(PathBuf, PathBuf)>, Option<TempDir>) {
+    let mut tasks = Vec::new();
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    if ext == "zip" {
+        if verbose {
+            println!("[DEBUG] Processando zip: {}", path.display());
+        }
+        let td = match tempdir() {
+            Ok(td) => td,
+            Err(e) => {
+                eprintln!("[ERROR] falha ao criar diretório temporário para {}: {}", path.display(), e);
+                return (tasks, None);
+            }
+        };
+        match safe_extract_zip(path, td.path(), verbose) {
+            Ok(extracted) => {
+                for ex in extracted {
+                    if ex.extension().and_then(|s| s.to_str()).map(|s| s.eq_ignore_ascii_case("xml")).unwrap_or(false) {
+                        let name = ex.file_name().and_then(|s| s.to_str()).unwrap_or("");
+                        if let Some(chave) = find_chave_for_xml(&ex, name, re, verbose) {
+                            if let Some(dest_dir) = destination_for_chave(base, &chave, today, validate, verbose) {
+                                tasks.push((ex.clone(), dest_dir));
+                            }
+                        } else if verbose {
+                            println!("[DEBUG] Nenhuma chave 44 dígitos em {}", ex.display());
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("[WARN] falha ao extrair zip {}: {}", path.display(), e);
             }
-            if dry_run  {
-                return Some(Ok(dest));
+        }
+        return (tasks, Some(td));
+    } else if ext == "xml" {
+        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+            if let Some(chave) = find_chave_for_xml(path, name, re, verbose) {
+                if let Some(dest_dir) = destination_for_chave(base, &chave, today, validate, verbose) {
+                    tasks.push((path.to_path_buf(), dest_dir));
+                }
+            } else if verbose {
+                println!("[DEBUG] Nenhuma chave 44 dígitos em {} (nome e conteúdo)", path.display());
             }
-            Some(fs::copy(src, &dest).map(|_| dest))
         }
     }
+    (tasks, None)
+}
 
+/// Executa, em paralelo via rayon, as tarefas de cópia já resolvidas por
+/// `collect_tasks_for_path`, deduplicando por conteúdo através de `digest_index`.
+/// Devolve (1) o `dest` real de cada `src` cuja cópia foi de fato realizada
+/// (em `--dry-run`, o `dest` previsto de cada cópia simulada; exclui
+/// duplicatas de conteúdo, que não copiam nada) e (2) os `src` cuja
+/// cópia falhou, para que o chamador não marque esses arquivos como
+/// processados em nenhum cache.
+fn run_copy_tasks(
+    tasks: &[(PathBuf, PathBuf)],
+    digest_index: &std::sync::Mutex<DigestIndex>,
+    dry_run: bool,
+    verbose: bool,
+) -> (HashMap<PathBuf, PathBuf>, HashSet<PathBuf>) {
+    let succeeded = std::sync::Mutex::new(HashMap::new());
+    let failed = std::sync::Mutex::new(HashSet::new());
+    tasks.par_iter().with_max_len(1).for_each(|(src, dest_dir)| {
+        match copy_file_to_dest(src, dest_dir, digest_index, dry_run, verbose) {
+            Some(Ok(dest)) => {
+                succeeded.lock().unwrap().insert(src.clone(), dest);
+            }
+            Some(Err(e)) => {
+                eprintln!("[ERROR] falha copiando {} -> {}: {}", src.display(), dest_dir.display(), e);
+                failed.lock().unwrap().insert(src.clone());
+            }
+            None => {}
+        }
+    });
+    (succeeded.into_inner().unwrap(), failed.into_inner().unwrap())
 }
 
-fn process_root(root: &Path, base: &Path, dry_run: bool, _: usize, verbose: bool) -> io::Result<()> {
+fn process_root(root: &Path, base: &Path, dry_run: bool, _: usize, validate: bool, excludes: &[String], verbose: bool) -> io::Result<()> {
     let re = Regex::new(CHAVE_RE_STR).unwrap();
     let today = Local::now().date_naive();
 
+    // Carrega o estado da varredura anterior e marca o instante em que esta
+    // varredura começou, para detectar arquivos "sujos" (ver is_unchanged).
+    let old_state = load_scan_state(base, verbose);
+    let scan_start = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let new_state = std::sync::Mutex::new(ScanState::new());
+    // Entradas de estado ainda não confirmadas: só são promovidas a
+    // new_state depois que run_copy_tasks garante que todas as cópias do
+    // respectivo "dono" (o .zip ou .xml varrido) tiveram sucesso.
+    let pending_state = std::sync::Mutex::new(HashMap::<PathBuf, ScanEntry>::new());
+    let owner_tasks = std::sync::Mutex::new(HashMap::<PathBuf, Vec<PathBuf>>::new());
+
     // Coleta de tarefas de cópia
     let copy_tasks = std::sync::Mutex::new(Vec::<(PathBuf, PathBuf)>::new());
+    // Mantém os tempdirs dos zips extraídos vivos até run_copy_tasks
+    // terminar; collect_tasks_for_path devolve as tarefas apontando para
+    // dentro deles, então destruí-los antes faria a cópia falhar.
+    let zip_tempdirs = std::sync::Mutex::new(Vec::<TempDir>::new());
+    let mut skipped = 0usize;
+
+    // Padrões de exclusão: os globs --exclude valem para toda a árvore; os
+    // arquivos .fiscalignore são descobertos e compostos por diretório à
+    // medida que o WalkDir desce, podando subárvores inteiras cedo.
+    let global_ignore = build_global_ignore(root, excludes);
+    let mut ignore_cache: HashMap<PathBuf, Vec<Gitignore>> = HashMap::new();
 
     // Primeiro varre todos os arquivos
-    for entry in WalkDir::new(root).follow_links(false).into_iter().filter_map(|e| e.ok()) {
+    let walker = WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.path() == root {
+                return true;
+            }
+            let parent = entry.path().parent().unwrap_or(root);
+            let chain = ignore_chain_for_dir(parent, root, &mut ignore_cache);
+            !is_path_ignored(entry.path(), entry.file_type().is_dir(), &global_ignore, &chain)
+        });
+    for entry in walker.filter_map(|e| e.ok()) {
         let path = entry.path().to_path_buf();
         if path.is_file() {
             let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
             if ext == "zip" {
-                if verbose {
-                    println!("[DEBUG] Processando zip: {}", path.display());
-                }
-                let td = tempdir()?;
-                match safe_extract_zip(&path, td.path(), verbose) {
-                    Ok(extracted) => {
-                        for ex in extracted {
-                            if ex.extension().and_then(|s| s.to_str()).map(|s| s.eq_ignore_ascii_case("xml")).unwrap_or(false) {
-                                if let Some(chave) = find_chave_in_name(&ex.file_name().and_then(|s| s.to_str()).unwrap_or(""), &re) {
-                                    if let Some(dest_dir) = determine_destination_for_xml(base, &chave, today, verbose) {
-                                        copy_tasks.lock().unwrap().push((ex.clone(), dest_dir));
-                                    }
-                                } else if verbose {
-                                    println!("[DEBUG] Nenhuma chave 44 dígitos em {}", ex.display());
-                                }
-                            }
-                        }
+                if let Some((size, mtime)) = is_unchanged(&path, &old_state, scan_start) {
+                    if verbose {
+                        println!("[DEBUG] Zip inalterado desde a última execução, pulando: {}", path.display());
                     }
-                    Err(e) => {
-                        eprintln!("[WARN] falha ao extrair zip {}: {}", path.display(), e);
+                    new_state.lock().unwrap().insert(path.clone(), ScanEntry { size, mtime, dest: "(zip)".to_string() });
+                    skipped += 1;
+                    continue;
+                }
+                let (new_tasks, td) = collect_tasks_for_path(&path, base, &re, today, validate, verbose);
+                if let Some(td) = td {
+                    zip_tempdirs.lock().unwrap().push(td);
+                }
+                if let Ok((size, mtime)) = file_size_mtime(&path) {
+                    let entry = ScanEntry { size, mtime, dest: "(zip)".to_string() };
+                    if new_tasks.is_empty() {
+                        // Nada para copiar (zip vazio ou sem XMLs reconhecidos):
+                        // não há cópia pendente que possa falhar, então o
+                        // estado já pode ser gravado.
+                        new_state.lock().unwrap().insert(path.clone(), entry);
+                    } else {
+                        let srcs = new_tasks.iter().map(|(src, _)| src.clone()).collect();
+                        owner_tasks.lock().unwrap().insert(path.clone(), srcs);
+                        pending_state.lock().unwrap().insert(path.clone(), entry);
                     }
                 }
-                // td é removido ao sair do escopo
+                copy_tasks.lock().unwrap().extend(new_tasks);
             } else if ext == "xml" {
-                if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-                    if let Some(chave) = find_chave_in_name(name, &re) {
-                        if let Some(dest_dir) = determine_destination_for_xml(base, &chave, today, verbose) {
-                            copy_tasks.lock().unwrap().push((path.clone(), dest_dir));
+                if let Some((size, mtime)) = is_unchanged(&path, &old_state, scan_start) {
+                    if verbose {
+                        println!("[DEBUG] XML inalterado desde a última execução, pulando: {}", path.display());
+                    }
+                    let dest = old_state.get(&path).map(|e| e.dest.clone()).unwrap_or_default();
+                    new_state.lock().unwrap().insert(path.clone(), ScanEntry { size, mtime, dest });
+                    skipped += 1;
+                    continue;
+                }
+                let (new_tasks, _td) = collect_tasks_for_path(&path, base, &re, today, validate, verbose);
+                if let Ok((size, mtime)) = file_size_mtime(&path) {
+                    match new_tasks.first() {
+                        Some((src, dest_dir)) => {
+                            let entry = ScanEntry { size, mtime, dest: dest_dir.display().to_string() };
+                            owner_tasks.lock().unwrap().insert(path.clone(), vec![src.clone()]);
+                            pending_state.lock().unwrap().insert(path.clone(), entry);
+                        }
+                        None => {
+                            // Sem chave (nome nem conteúdo): não há nada para
+                            // copiar, então nada pode falhar depois — assim
+                            // como no branch de zip, já cacheia direto, senão
+                            // esse arquivo seria reprocessado para sempre.
+                            let entry = ScanEntry { size, mtime, dest: String::new() };
+                            new_state.lock().unwrap().insert(path.clone(), entry);
                         }
-                    } else if verbose {
-                        println!("[DEBUG] Nenhuma chave 44 dígitos em {}", path.display());
                     }
                 }
+                copy_tasks.lock().unwrap().extend(new_tasks);
             }
         }
     }
 
     let tasks = copy_tasks.into_inner().unwrap();
     let total = tasks.len();
-    println!("[INFO] Tarefas de cópia a executar: {}", total);
+    println!("[INFO] Tarefas de cópia a executar: {} ({} pulados por estarem em cache)", total, skipped);
 
-    // Executa cópias em paralelo usando rayon
-    tasks.par_iter().with_max_len(1).for_each(|(src, dest_dir)| {
-        if let Some(Err(e)) = copy_file_to_dest(src, dest_dir, dry_run, verbose) {
-            eprintln!("[ERROR] falha copiando {} -> {}: {}", src.display(), dest_dir.display(), e);
+    // Índice de digests SHA-256 já armazenados em --dest, para deduplicar
+    // conteúdo idêntico mesmo quando o nome do arquivo de origem muda.
+    let digest_index = std::sync::Mutex::new(load_digest_index(base, verbose));
+
+    let (_, failed_srcs) = run_copy_tasks(&tasks, &digest_index, dry_run, verbose);
+    // Só agora os zips extraídos podem ser limpos: as cópias já terminaram.
+    drop(zip_tempdirs.into_inner().unwrap());
+
+    // Só promove o estado pendente de um dono (zip ou xml) para new_state
+    // se nenhuma das cópias que ele originou falhou; do contrário o arquivo
+    // continuará "sujo" e será reprocessado na próxima execução.
+    let mut new_state = new_state.into_inner().unwrap();
+    if !dry_run {
+        for (owner, srcs) in owner_tasks.into_inner().unwrap() {
+            if srcs.iter().all(|s| !failed_srcs.contains(s)) {
+                if let Some(entry) = pending_state.lock().unwrap().remove(&owner) {
+                    new_state.insert(owner, entry);
+                }
+            }
         }
-    });
+
+        if let Err(e) = save_scan_state(base, &new_state, verbose) {
+            eprintln!("[WARN] falha ao salvar estado da varredura: {}", e);
+        }
+        if let Err(e) = save_digest_index(base, &digest_index.into_inner().unwrap(), verbose) {
+            eprintln!("[WARN] falha ao salvar índice de digests: {}", e);
+        }
+    }
 
     println!("[INFO] Concluído: {} cópias (simulação: {})", total, dry_run);
     Ok(())
 }
 
+fn is_candidate_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("xml") || ext.eq_ignore_ascii_case("zip"))
+        .unwrap_or(false)
+}
+
+/// Modo `--watch`: em vez de varrer `root` uma única vez, fica monitorando-o
+/// via `notify` e organiza cada `.xml`/`.zip` assim que ele "assenta" (fica
+/// sem novos eventos por `DEBOUNCE_DURATION`), reusando o mesmo pipeline de
+/// `collect_tasks_for_path`/`run_copy_tasks` da varredura em lote.
+fn run_watch(root: &Path, base: &Path, dry_run: bool, validate: bool, verbose: bool) -> io::Result<()> {
+    use notify::{RecursiveMode, Watcher};
+    use std::sync::mpsc::{channel, RecvTimeoutError};
+    use std::time::{Duration, Instant};
+
+    const DEBOUNCE_DURATION: Duration = Duration::from_millis(800);
+
+    println!("[INFO] Modo --watch ativo, monitorando: {}", root.display());
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    let re = Regex::new(CHAVE_RE_STR).unwrap();
+    let digest_index = std::sync::Mutex::new(load_digest_index(base, verbose));
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+    loop {
+        match rx.recv_timeout(DEBOUNCE_DURATION) {
+            Ok(Ok(event)) => {
+                for path in event.paths {
+                    if is_candidate_file(&path) {
+                        pending.insert(path, Instant::now());
+                    }
+                }
+                continue;
+            }
+            Ok(Err(e)) => {
+                eprintln!("[WARN] erro do watcher: {}", e);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, seen_at)| now.duration_since(**seen_at) >= DEBOUNCE_DURATION)
+            .map(|(path, _)| path.clone())
+            .collect();
+        if settled.is_empty() {
+            continue;
+        }
+        for path in &settled {
+            pending.remove(path);
+        }
+
+        let today = Local::now().date_naive();
+        let mut batch_tasks = Vec::new();
+        // Mantidos vivos até o fim da cópia do lote, pelo mesmo motivo da
+        // varredura em lote: as tarefas de um zip apontam para dentro deles.
+        let mut batch_tempdirs = Vec::new();
+        for path in &settled {
+            if path.exists() {
+                let (tasks, td) = collect_tasks_for_path(path, base, &re, today, validate, verbose);
+                batch_tasks.extend(tasks);
+                if let Some(td) = td {
+                    batch_tempdirs.push(td);
+                }
+            }
+        }
+        if batch_tasks.is_empty() {
+            continue;
+        }
+
+        let (succeeded, _) = run_copy_tasks(&batch_tasks, &digest_index, dry_run, verbose);
+        drop(batch_tempdirs);
+        for (src, dest) in &succeeded {
+            println!("[INFO] Organizado: {} -> {}", src.display(), dest.display());
+        }
+        if !dry_run {
+            if let Err(e) = save_digest_index(base, &digest_index.lock().unwrap(), verbose) {
+                eprintln!("[WARN] falha ao salvar índice de digests: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn main() -> io::Result<()> {
     let args = Args::parse();
     let root = args.root.unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from("~/")));
@@ -319,7 +1044,11 @@ fn main() -> io::Result<()> {
 
     ensure_directories(&dest_base, args.verbose)?;
 
-    process_root(&root, &dest_base, args.dry_run, args.workers, args.verbose)?;
+    if args.watch {
+        run_watch(&root, &dest_base, args.dry_run, args.validate, args.verbose)?;
+    } else {
+        process_root(&root, &dest_base, args.dry_run, args.workers, args.validate, &args.excludes, args.verbose)?;
+    }
 
     Ok(())
 }